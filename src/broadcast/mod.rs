@@ -0,0 +1,278 @@
+use crate::{
+    multi_channel,
+    util::{
+        codec::EncodeMethod,
+        split::TcpSplit,
+    },
+};
+use errors::*;
+use futures::{ready, Sink};
+use snafu::{Backtrace, ResultExt};
+use std::{collections::VecDeque, fmt, net::SocketAddr, sync::Mutex, task::Poll};
+use tokio::io::AsyncWrite;
+
+struct ReplayBuffer<T> {
+    capacity: usize,
+    items: VecDeque<(u64, T)>,
+    next_seq: u64,
+}
+
+impl<T: Clone> ReplayBuffer<T> {
+    fn new(capacity: usize) -> Self {
+        ReplayBuffer {
+            capacity,
+            items: VecDeque::with_capacity(capacity),
+            next_seq: 0,
+        }
+    }
+
+    fn push(&mut self, item: T) {
+        self.items.push_back((self.next_seq, item));
+        self.next_seq += 1;
+
+        while self.items.len() > self.capacity {
+            self.items.pop_front();
+        }
+    }
+
+    /// The oldest sequence number still retained (equal to `next_seq` when
+    /// the buffer is empty, i.e. nothing to catch up on yet).
+    fn oldest_seq(&self) -> u64 {
+        self.items.front().map(|(seq, _)| *seq).unwrap_or(self.next_seq)
+    }
+
+    fn get(&self, seq: u64) -> Option<&T> {
+        self.items.front().and_then(|(front_seq, _)| {
+            seq.checked_sub(*front_seq)
+                .and_then(|offset| self.items.get(offset as usize))
+                .map(|(_, item)| item)
+        })
+    }
+}
+
+struct Replay<T> {
+    buffer: Mutex<ReplayBuffer<T>>,
+}
+
+/// The write half of a split [`crate::mpsc::Receiver`]; broadcasts every sent
+/// item to all currently-connected peers.
+///
+/// With [`Sender::with_replay_buffer`], it also retains the last N sent
+/// items in a ring and replays them, oldest-first, to every peer admitted by
+/// [`Sender::accept`] before that peer starts receiving live traffic. Because
+/// `accept` takes `&mut self` for the whole replay, nothing can race it to
+/// push a new item (and evict an old one) while it's under way — so a replay
+/// always sees every item that was in the buffer when it started, with
+/// nothing to skip or stall on.
+#[pin_project::pin_project]
+pub struct Sender<T: fmt::Debug, E, const N: usize = 0, RW = TcpSplit> {
+    #[pin]
+    channel: multi_channel::Channel<T, E, N, RW>,
+    replay: Option<Replay<T>>,
+}
+
+impl<T, E, const N: usize, RW> Sender<T, E, N, RW>
+where
+    T: fmt::Debug,
+{
+    pub(crate) fn from_channel(channel: multi_channel::Channel<T, E, N, RW>) -> Self {
+        Sender {
+            channel,
+            replay: None,
+        }
+    }
+
+    /// Opts in to buffering the last `capacity` sent items for replay to
+    /// late-joining subscribers.
+    pub fn with_replay_buffer(mut self, capacity: usize) -> Self
+    where
+        T: Clone,
+    {
+        self.replay = Some(Replay {
+            buffer: Mutex::new(ReplayBuffer::new(capacity)),
+        });
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.channel.len()
+    }
+
+    pub fn local_addr(&self) -> &SocketAddr {
+        self.channel.local_addr()
+    }
+
+    pub fn peer_addrs(&self) -> Vec<SocketAddr> {
+        self.channel.peer_addrs()
+    }
+}
+
+impl<T, E, const N: usize> Sender<T, E, N>
+where
+    T: 'static + Clone + fmt::Debug,
+    E: 'static + EncodeMethod<T>,
+{
+    /// Accepts a new subscriber and, if a replay buffer is configured,
+    /// catches it up on buffered history before returning.
+    pub async fn accept(&mut self) -> Result<SocketAddr, BroadcastAcceptingError<T, E>> {
+        let addr = self
+            .channel
+            .accept()
+            .await
+            .context(AcceptSnafu)?;
+
+        if self.replay.is_some() {
+            self.replay_to(&addr).await?;
+        }
+
+        Ok(addr)
+    }
+
+    async fn replay_to(&mut self, addr: &SocketAddr) -> Result<(), BroadcastAcceptingError<T, E>> {
+        let replay = self.replay.as_ref().expect("replay buffer configured");
+        let mut cursor = replay.buffer.lock().unwrap().oldest_seq();
+
+        loop {
+            let item = replay.buffer.lock().unwrap().get(cursor).cloned();
+
+            match item {
+                Some(item) => {
+                    self.channel
+                        .send_to(addr, item)
+                        .await
+                        .context(ReplaySnafu)?;
+                    cursor += 1;
+                }
+                None => return Ok(()),
+            }
+        }
+    }
+}
+
+impl<T, E, const N: usize, RW> Sink<T> for Sender<T, E, N, RW>
+where
+    T: 'static + Clone + fmt::Debug,
+    E: 'static + EncodeMethod<T>,
+    RW: AsyncWrite,
+{
+    type Error = BroadcastError<T, E>;
+
+    fn start_send(self: std::pin::Pin<&mut Self>, item: T) -> Result<(), Self::Error> {
+        let this = self.project();
+
+        if let Some(replay) = this.replay {
+            replay.buffer.lock().unwrap().push(item.clone());
+        }
+
+        this.channel.start_send(item).context(SendSnafu)
+    }
+
+    fn poll_ready(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        if let Err(e) = ready!(self.project().channel.poll_ready(cx)) {
+            return Poll::Ready(Err(e).context(SendSnafu));
+        }
+
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_flush(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        if let Err(e) = ready!(self.project().channel.poll_flush(cx)) {
+            return Poll::Ready(Err(e).context(SendSnafu));
+        }
+
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        if let Err(e) = ready!(self.project().channel.poll_close(cx)) {
+            return Poll::Ready(Err(e).context(SendSnafu));
+        }
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+pub mod errors {
+    use super::*;
+    use snafu::Snafu;
+
+    #[derive(Debug, Snafu)]
+    #[snafu(visibility(pub(super)))]
+    pub enum BroadcastAcceptingError<T, E>
+    where
+        T: 'static + fmt::Debug,
+        E: 'static + EncodeMethod<T>,
+        E::Error: 'static + fmt::Debug + std::error::Error,
+    {
+        #[snafu(display("[BroadcastAcceptingError] Failed to accept subscriber"))]
+        Accept {
+            source: multi_channel::errors::AcceptingError<T>,
+            backtrace: Backtrace,
+        },
+
+        #[snafu(display("[BroadcastAcceptingError] Failed to replay buffered history to new subscriber"))]
+        Replay {
+            source: multi_channel::errors::ChannelSinkError<T, E>,
+            backtrace: Backtrace,
+        },
+    }
+
+    #[derive(Debug, Snafu)]
+    #[snafu(display("[BroadcastError] Failed to send item on broadcast::Sender"))]
+    #[snafu(visibility(pub(super)))]
+    pub struct BroadcastError<T, E>
+    where
+        T: 'static + fmt::Debug,
+        E: 'static + EncodeMethod<T>,
+        E::Error: 'static + fmt::Debug + std::error::Error,
+    {
+        source: multi_channel::errors::ChannelSinkError<T, E>,
+        backtrace: Backtrace,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ReplayBuffer;
+
+    #[test]
+    fn get_returns_none_before_anything_pushed() {
+        let buffer: ReplayBuffer<u32> = ReplayBuffer::new(2);
+
+        assert_eq!(buffer.oldest_seq(), 0);
+        assert_eq!(buffer.get(0), None);
+    }
+
+    #[test]
+    fn get_returns_pushed_items_by_sequence() {
+        let mut buffer = ReplayBuffer::new(2);
+        buffer.push("a");
+        buffer.push("b");
+
+        assert_eq!(buffer.oldest_seq(), 0);
+        assert_eq!(buffer.get(0), Some(&"a"));
+        assert_eq!(buffer.get(1), Some(&"b"));
+    }
+
+    #[test]
+    fn push_past_capacity_evicts_the_oldest() {
+        let mut buffer = ReplayBuffer::new(2);
+        buffer.push(1);
+        buffer.push(2);
+        buffer.push(3);
+
+        assert_eq!(buffer.oldest_seq(), 1);
+        assert_eq!(buffer.get(0), None);
+        assert_eq!(buffer.get(1), Some(&2));
+        assert_eq!(buffer.get(2), Some(&3));
+    }
+}
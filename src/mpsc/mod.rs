@@ -155,34 +155,126 @@ where
     }
 }
 
+/// A peer connecting to or disconnecting from a [`Receiver`]'s listener.
+///
+/// Subscribe with [`Receiver::events`] to maintain a live membership set
+/// (e.g. to purge per-peer state) without disturbing the main `recv` path.
+#[derive(Debug, Clone)]
+pub enum PeerEvent {
+    Connected(SocketAddr),
+    /// `Some` with a rendering of the error when the disconnect is noticed
+    /// right after `recv_with_addr` or `recv_frame_with_addr` returned an
+    /// error for that peer; `None` otherwise, including every disconnect
+    /// noticed through `recv`, `recv_frame`, the `Stream` impl or `accept`,
+    /// whatever its cause.
+    Disconnected(SocketAddr, Option<String>),
+}
+
+/// Stream of [`PeerEvent`]s returned by [`Receiver::events`]. Lagged
+/// notifications (the subscriber fell behind the internal broadcast) are
+/// skipped rather than surfaced, since membership can always be rebuilt from
+/// [`Receiver::peer_addrs`].
+pub struct PeerEvents(tokio::sync::broadcast::Receiver<PeerEvent>);
+
+impl Stream for PeerEvents {
+    type Item = PeerEvent;
+
+    fn poll_next(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        use tokio::sync::broadcast::error::RecvError;
+
+        let this = self.get_mut();
+
+        loop {
+            match std::pin::pin!(this.0.recv()).poll(cx) {
+                Poll::Ready(Ok(event)) => return Poll::Ready(Some(event)),
+                Poll::Ready(Err(RecvError::Lagged(_))) => continue,
+                Poll::Ready(Err(RecvError::Closed)) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
 #[derive(Debug)]
 #[pin_project::pin_project]
-pub struct Receiver<T, E, const N: usize = 0, RW = TcpSplit>(
-    #[pin] multi_channel::Channel<T, E, N, RW>,
-);
+pub struct Receiver<T, E, const N: usize = 0, RW = TcpSplit> {
+    #[pin]
+    channel: multi_channel::Channel<T, E, N, RW>,
+    events: tokio::sync::broadcast::Sender<PeerEvent>,
+    known_peers: std::collections::HashSet<SocketAddr>,
+}
 
 impl<T, E, const N: usize, RW> Receiver<T, E, N, RW> {
     pub(crate) fn from_channel(channel: multi_channel::Channel<T, E, N, RW>) -> Self {
-        Self(channel)
+        let (events, _) = tokio::sync::broadcast::channel(64);
+
+        Self {
+            channel,
+            events,
+            known_peers: std::collections::HashSet::new(),
+        }
     }
 
     pub fn len(&self) -> usize {
-        self.0.len()
+        self.channel.len()
     }
 
     pub fn limit(&self) -> Option<usize> {
-        self.0.limit()
+        self.channel.limit()
     }
 
     pub fn local_addr(&self) -> &SocketAddr {
-        self.0.local_addr()
+        self.channel.local_addr()
     }
 
     pub fn peer_addrs(&self) -> Vec<SocketAddr> {
-        self.0.peer_addrs()
+        self.channel.peer_addrs()
+    }
+
+    /// Subscribes to this receiver's connect/disconnect lifecycle events.
+    /// Multiple independent subscribers can observe the same receiver.
+    pub fn events(&self) -> PeerEvents {
+        PeerEvents(self.events.subscribe())
+    }
+
+    /// Compares the channel's current peers against the last known set,
+    /// emitting `Disconnected` for everyone that dropped off since. `Stream`'s
+    /// `poll_next` only has pin-projected fields, not `&mut self`, so it
+    /// calls the free `reconcile_peers` function below directly instead of
+    /// this method; both share the same diffing logic.
+    fn reconcile_peers(&mut self, disconnected_with: Option<(SocketAddr, String)>) {
+        reconcile_peers(&self.channel, &self.events, &mut self.known_peers, disconnected_with);
     }
 }
 
+/// Diffs `channel`'s current peers against `known_peers`, emitting
+/// `Disconnected` for everyone that dropped off since and updating
+/// `known_peers` to match. Every place that touches the channel — a read, an
+/// accept, or a poll — runs this, so a disconnect is caught at the next
+/// activity of any of those kinds rather than only the next `recv`.
+fn reconcile_peers<T, E, const N: usize, RW>(
+    channel: &multi_channel::Channel<T, E, N, RW>,
+    events: &tokio::sync::broadcast::Sender<PeerEvent>,
+    known_peers: &mut std::collections::HashSet<SocketAddr>,
+    disconnected_with: Option<(SocketAddr, String)>,
+) {
+    let current: std::collections::HashSet<SocketAddr> = channel.peer_addrs().into_iter().collect();
+
+    for addr in known_peers.difference(&current) {
+        let reason = disconnected_with
+            .as_ref()
+            .filter(|(with_addr, _)| with_addr == addr)
+            .map(|(_, reason)| reason.clone());
+
+        let _ = events.send(PeerEvent::Disconnected(*addr, reason));
+    }
+
+    *known_peers = current;
+}
+
 impl<T, E, const N: usize, R, W> Receiver<T, E, N, RWSplit<R, W>> {
     pub fn split(
         self,
@@ -191,13 +283,23 @@ impl<T, E, const N: usize, R, W> Receiver<T, E, N, RWSplit<R, W>> {
         multi_channel::errors::SplitError,
     > {
         let readhalf_is_listener = true;
-        self.0.split(readhalf_is_listener)
+        self.channel.split(readhalf_is_listener)
     }
 }
 
 impl<T, E, const N: usize> Receiver<T, E, N> {
     pub async fn accept(&mut self) -> Result<SocketAddr, ReceiverAcceptingError<TcpSplit>> {
-        self.0.accept().await.context(ReceiverAcceptingSnafu)
+        let addr = self.channel.accept().await.context(ReceiverAcceptingSnafu)?;
+
+        // An accept is also a natural point to notice that some other peer
+        // has dropped off since the last time anything touched the channel,
+        // rather than waiting for the next `recv`.
+        self.reconcile_peers(None);
+
+        self.known_peers.insert(addr);
+        let _ = self.events.send(PeerEvent::Connected(addr));
+
+        Ok(addr)
     }
 }
 
@@ -209,30 +311,53 @@ impl<
     > Receiver<T, E, N, RW>
 {
     pub async fn recv(&mut self) -> Option<Result<T, ReceiverError<T, E>>> {
-        self.0.next().await.map(|res| res.context(ReceiverSnafu))
+        let result = self.channel.next().await.map(|res| res.context(ReceiverSnafu));
+        self.reconcile_peers(None);
+        result
     }
 
     pub async fn recv_with_addr(&mut self) -> Option<(Result<T, ReceiverError<T, E>>, SocketAddr)> {
-        self.0
+        let result = self
+            .channel
             .recv_with_addr()
             .await
-            .map(|(res, addr)| (res.context(ReceiverSnafu), addr))
+            .map(|(res, addr)| (res.context(ReceiverSnafu), addr));
+
+        let disconnected_with = match &result {
+            Some((Err(e), addr)) => Some((*addr, format!("{e}"))),
+            _ => None,
+        };
+        self.reconcile_peers(disconnected_with);
+
+        result
     }
 
     pub async fn recv_frame(&mut self) -> Option<Result<BytesMut, ReceiverError<T, E>>> {
-        self.0
+        let result = self
+            .channel
             .recv_frame()
             .await
-            .map(|res| res.context(ReceiverSnafu))
+            .map(|res| res.context(ReceiverSnafu));
+        self.reconcile_peers(None);
+        result
     }
 
     pub async fn recv_frame_with_addr(
         &mut self,
     ) -> Option<(Result<BytesMut, ReceiverError<T, E>>, SocketAddr)> {
-        self.0
+        let result = self
+            .channel
             .recv_frame_with_addr()
             .await
-            .map(|(res, addr)| (res.context(ReceiverSnafu), addr))
+            .map(|(res, addr)| (res.context(ReceiverSnafu), addr));
+
+        let disconnected_with = match &result {
+            Some((Err(e), addr)) => Some((*addr, format!("{e}"))),
+            _ => None,
+        };
+        self.reconcile_peers(disconnected_with);
+
+        result
     }
 }
 
@@ -249,7 +374,13 @@ impl<
         self: std::pin::Pin<&mut Self>,
         cx: &mut std::task::Context<'_>,
     ) -> Poll<Option<Self::Item>> {
-        match ready!(self.project().0.poll_next(cx)) {
+        let mut this = self.project();
+
+        let item = ready!(this.channel.as_mut().poll_next(cx));
+
+        reconcile_peers(&*this.channel, this.events, this.known_peers, None);
+
+        match item {
             Some(Ok(item)) => Poll::Ready(Some(Ok(item))),
             Some(Err(e)) => Poll::Ready(Some(Err(e).context(ReceiverSnafu))),
             None => Poll::Ready(None),
@@ -0,0 +1,39 @@
+use snafu::Snafu;
+use std::io;
+
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub(super)))]
+pub enum ConnectError {
+    #[snafu(display("[ConnectError] Failed to connect to rpc server"))]
+    Connect { source: io::Error },
+}
+
+/// Failure mode of a single [`Client::call`](super::Client::call).
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub(super)))]
+pub enum RpcError {
+    #[snafu(display("[RpcError] Failed to encode request"))]
+    Encode { source: Box<dyn std::error::Error + Send + Sync> },
+
+    #[snafu(display("[RpcError] Failed to decode response"))]
+    Decode { source: Box<dyn std::error::Error + Send + Sync> },
+
+    #[snafu(display("[RpcError] Failed to write request frame"))]
+    Write { source: io::Error },
+
+    #[snafu(display("[RpcError] Server returned an error frame: {message}"))]
+    Server { message: String },
+
+    #[snafu(display("[RpcError] Connection closed before a response arrived"))]
+    ConnectionClosed,
+}
+
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub(super)))]
+pub enum ServeError {
+    #[snafu(display("[ServeError] Failed to bind rpc server"))]
+    Bind { source: io::Error },
+
+    #[snafu(display("[ServeError] Failed to accept rpc connection"))]
+    Accept { source: io::Error },
+}
@@ -0,0 +1,131 @@
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use tokio::io::{self, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Largest payload a frame is allowed to declare. Rejected before the
+/// payload buffer is allocated, so a peer can't force an arbitrarily large
+/// allocation just by sending a crafted length prefix.
+pub const MAX_FRAME_LEN: u32 = 16 * 1024 * 1024;
+
+/// One byte tag written ahead of every frame, identifying what the payload
+/// that follows means to the peer on the other end of the request id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum FrameKind {
+    Request = 0,
+    Response = 1,
+    Error = 2,
+    /// Terminates a streaming response's request id; no further `Response`
+    /// frames for that id will follow.
+    EndOfStream = 3,
+    /// Sent by the client when it drops a `call_streaming` response before
+    /// `EndOfStream` arrives, so the server can stop driving that request
+    /// id's handler stream instead of running it to completion unread.
+    Cancel = 4,
+}
+
+impl FrameKind {
+    fn from_u8(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(FrameKind::Request),
+            1 => Some(FrameKind::Response),
+            2 => Some(FrameKind::Error),
+            3 => Some(FrameKind::EndOfStream),
+            4 => Some(FrameKind::Cancel),
+            _ => None,
+        }
+    }
+}
+
+/// A single demultiplexable frame: a request id, a [`FrameKind`], and the
+/// codec-encoded payload.
+#[derive(Debug)]
+pub struct Frame {
+    pub id: u64,
+    pub kind: FrameKind,
+    pub payload: BytesMut,
+}
+
+/// Writes `id`, `kind`, and `payload` (length-prefixed) to `writer`.
+pub async fn write_frame<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    id: u64,
+    kind: FrameKind,
+    payload: &[u8],
+) -> io::Result<()> {
+    let mut header = BytesMut::with_capacity(13);
+    header.put_u64(id);
+    header.put_u8(kind as u8);
+    header.put_u32(payload.len() as u32);
+
+    writer.write_all(&header).await?;
+    writer.write_all(payload).await?;
+    writer.flush().await
+}
+
+/// Reads the next frame off `reader`, or `Ok(None)` on a clean EOF between
+/// frames.
+pub async fn read_frame<R: AsyncRead + Unpin>(reader: &mut R) -> io::Result<Option<Frame>> {
+    let mut header = [0u8; 13];
+    match reader.read_exact(&mut header).await {
+        Ok(_) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+
+    let mut header = Bytes::copy_from_slice(&header);
+    let id = header.get_u64();
+    let kind = FrameKind::from_u8(header.get_u8())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "unknown rpc frame kind"))?;
+    let len = header.get_u32();
+    if len > MAX_FRAME_LEN {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("rpc frame of {len} bytes exceeds MAX_FRAME_LEN ({MAX_FRAME_LEN})"),
+        ));
+    }
+
+    let mut payload = BytesMut::zeroed(len as usize);
+    reader.read_exact(&mut payload).await?;
+
+    Ok(Some(Frame { id, kind, payload }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn write_then_read_round_trips_a_frame() {
+        let mut buf = Vec::new();
+        write_frame(&mut buf, 42, FrameKind::Response, b"hello")
+            .await
+            .unwrap();
+
+        let mut cursor = std::io::Cursor::new(buf);
+        let frame = read_frame(&mut cursor).await.unwrap().unwrap();
+
+        assert_eq!(frame.id, 42);
+        assert_eq!(frame.kind, FrameKind::Response);
+        assert_eq!(&frame.payload[..], b"hello");
+    }
+
+    #[tokio::test]
+    async fn read_frame_returns_none_on_clean_eof_between_frames() {
+        let mut cursor = std::io::Cursor::new(Vec::new());
+
+        assert!(read_frame(&mut cursor).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn read_frame_rejects_a_length_prefix_over_max_frame_len() {
+        let mut header = BytesMut::with_capacity(13);
+        header.put_u64(0);
+        header.put_u8(FrameKind::Request as u8);
+        header.put_u32(MAX_FRAME_LEN + 1);
+
+        let mut cursor = std::io::Cursor::new(header.to_vec());
+        let err = read_frame(&mut cursor).await.unwrap_err();
+
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
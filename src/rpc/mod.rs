@@ -0,0 +1,504 @@
+//! Correlated request/response RPC multiplexed over a single TCP connection,
+//! in the spirit of packet-stream/intercom: every frame carries a
+//! monotonically increasing request id so many calls can be in flight at
+//! once without each needing its own connection.
+
+use crate::util::codec::{DecodeMethod, EncodeMethod};
+use errors::*;
+use frame::{read_frame, write_frame, Frame, FrameKind};
+use futures::{Stream, StreamExt};
+use snafu::ResultExt;
+use std::{
+    collections::HashMap,
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    task::{self, Poll},
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    sync::{mpsc, oneshot},
+};
+
+mod frame;
+pub mod errors;
+
+#[cfg(feature = "json")]
+pub type JsonClient<Req, Resp, W = tokio::net::tcp::OwnedWriteHalf> =
+    Client<Req, Resp, crate::util::codec::JsonCodec, W>;
+
+#[cfg(feature = "protobuf")]
+pub type ProtobufClient<Req, Resp, W = tokio::net::tcp::OwnedWriteHalf> =
+    Client<Req, Resp, crate::util::codec::ProtobufCodec, W>;
+
+#[cfg(feature = "rkyv")]
+pub type RkyvClient<Req, Resp, W = tokio::net::tcp::OwnedWriteHalf> =
+    Client<Req, Resp, crate::util::codec::RkyvCodec, W>;
+
+/// What a request id is waiting on: a single reply, or a running stream of
+/// them.
+enum Pending<Resp> {
+    Unary(oneshot::Sender<Result<Resp, RpcError>>),
+    Streaming(mpsc::UnboundedSender<Result<Resp, RpcError>>),
+}
+
+type InflightMap<Resp> = Arc<Mutex<HashMap<u64, Pending<Resp>>>>;
+
+/// Connects to an `rpc` [`serve_on`] listener at `dest` and returns a
+/// [`Client`] for issuing calls over the resulting connection.
+pub async fn connect_to<A, Req, Resp, E>(dest: A) -> Result<Client<Req, Resp, E>, ConnectError>
+where
+    A: ToSocketAddrs,
+    Req: 'static + Send,
+    Resp: 'static + Send,
+    E: 'static + DecodeMethod<Resp>,
+{
+    let stream = TcpStream::connect(dest).await.context(ConnectSnafu)?;
+    let (read, write) = stream.into_split();
+
+    Ok(Client::new(read, write))
+}
+
+/// Binds `local_addr` and serves every accepted connection with `handler`,
+/// spawning one task per connection.
+pub async fn serve_on<A, Req, Resp, E, F, Fut>(
+    local_addr: A,
+    handler: F,
+) -> Result<(), ServeError>
+where
+    A: ToSocketAddrs,
+    Req: 'static + Send,
+    Resp: 'static + Send,
+    E: 'static + DecodeMethod<Req> + EncodeMethod<Resp>,
+    F: 'static + Clone + Send + Fn(Req) -> Fut,
+    Fut: 'static + Send + Future<Output = Resp>,
+{
+    let listener = TcpListener::bind(local_addr).await.context(BindSnafu)?;
+
+    loop {
+        let (stream, _addr) = listener.accept().await.context(AcceptSnafu)?;
+        let (read, write) = stream.into_split();
+        let handler = handler.clone();
+
+        tokio::spawn(async move {
+            let _ = serve_connection::<Req, Resp, E, F, Fut, _, _>(read, write, handler).await;
+        });
+    }
+}
+
+/// A single in-flight request; removes itself from the client's map if
+/// dropped before a response arrives, so a cancelled call doesn't leak.
+struct CallGuard<Resp> {
+    id: u64,
+    inflight: InflightMap<Resp>,
+    completed: bool,
+}
+
+impl<Resp> Drop for CallGuard<Resp> {
+    fn drop(&mut self) {
+        if !self.completed {
+            self.inflight.lock().unwrap().remove(&self.id);
+        }
+    }
+}
+
+/// Issues correlated request/response calls over a single connection; many
+/// calls may be in flight at once.
+pub struct Client<Req, Resp, E, W = tokio::net::tcp::OwnedWriteHalf> {
+    next_id: Arc<AtomicU64>,
+    inflight: InflightMap<Resp>,
+    write: Arc<tokio::sync::Mutex<W>>,
+    _codec: std::marker::PhantomData<fn(Req, E)>,
+}
+
+impl<Req, Resp, E> Client<Req, Resp, E>
+where
+    Req: 'static + Send,
+    Resp: 'static + Send,
+    E: 'static + DecodeMethod<Resp>,
+{
+    /// Wraps an already-connected, already-split duplex connection as a
+    /// `Client`. `connect_to` is the usual entry point for plain TCP; this is
+    /// for callers supplying their own transport (TLS, an in-memory
+    /// `tokio::io::duplex` pair in a test, etc).
+    pub fn from_parts<R, W>(read: R, write: W) -> Client<Req, Resp, E, W>
+    where
+        R: 'static + AsyncRead + Unpin + Send,
+        W: 'static + AsyncWrite + Unpin + Send,
+    {
+        Client::new(read, write)
+    }
+
+    pub(crate) fn new<R, W>(read: R, write: W) -> Client<Req, Resp, E, W>
+    where
+        R: 'static + AsyncRead + Unpin + Send,
+        W: 'static + AsyncWrite + Unpin + Send,
+    {
+        let inflight: InflightMap<Resp> = Arc::new(Mutex::new(HashMap::new()));
+        let reader_inflight = Arc::clone(&inflight);
+
+        tokio::spawn(Self::demultiplex(read, reader_inflight));
+
+        Client {
+            next_id: Arc::new(AtomicU64::new(0)),
+            inflight,
+            write: Arc::new(tokio::sync::Mutex::new(write)),
+            _codec: std::marker::PhantomData,
+        }
+    }
+
+    /// Reads frames off the connection and routes each to the matching
+    /// in-flight entry by id. A read error or EOF fails every outstanding
+    /// call rather than letting them hang forever.
+    async fn demultiplex(mut read: impl AsyncRead + Unpin, inflight: InflightMap<Resp>) {
+        loop {
+            let frame = match read_frame(&mut read).await {
+                Ok(Some(frame)) => frame,
+                Ok(None) | Err(_) => break,
+            };
+
+            let Frame { id, kind, payload } = frame;
+
+            match kind {
+                // Only a client ever sends these; a well-behaved server
+                // never echoes them back.
+                FrameKind::Request | FrameKind::Cancel => continue,
+                FrameKind::EndOfStream => {
+                    // Dropping the sender closes the client's stream.
+                    inflight.lock().unwrap().remove(&id);
+                }
+                FrameKind::Response => {
+                    let result = E::decode(payload).map_err(|e| Box::new(e) as _).context(DecodeSnafu);
+                    let mut map = inflight.lock().unwrap();
+
+                    match map.get(&id) {
+                        Some(Pending::Streaming(tx)) => {
+                            let _ = tx.send(result);
+                        }
+                        Some(Pending::Unary(_)) => {
+                            if let Some(Pending::Unary(tx)) = map.remove(&id) {
+                                let _ = tx.send(result);
+                            }
+                        }
+                        None => {}
+                    }
+                }
+                FrameKind::Error => {
+                    let message = String::from_utf8_lossy(&payload).into_owned();
+
+                    if let Some(pending) = inflight.lock().unwrap().remove(&id) {
+                        match pending {
+                            Pending::Unary(tx) => {
+                                let _ = tx.send(Err(RpcError::Server { message }));
+                            }
+                            Pending::Streaming(tx) => {
+                                let _ = tx.send(Err(RpcError::Server { message }));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut inflight = inflight.lock().unwrap();
+        for (_, pending) in inflight.drain() {
+            match pending {
+                Pending::Unary(tx) => {
+                    let _ = tx.send(Err(RpcError::ConnectionClosed));
+                }
+                Pending::Streaming(tx) => {
+                    let _ = tx.send(Err(RpcError::ConnectionClosed));
+                }
+            }
+        }
+    }
+}
+
+impl<Req, Resp, E, W> Client<Req, Resp, E, W>
+where
+    Req: 'static + Send,
+    Resp: 'static + Send,
+    E: 'static + EncodeMethod<Req>,
+    W: AsyncWrite + Unpin,
+{
+    /// Sends `req` and resolves once the correlated response (or error)
+    /// frame arrives. Dropping the returned future before it resolves
+    /// cancels the call and frees its slot in the in-flight map.
+    pub async fn call(&self, req: Req) -> Result<Resp, RpcError> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = oneshot::channel();
+
+        self.inflight.lock().unwrap().insert(id, Pending::Unary(tx));
+        let mut guard = CallGuard {
+            id,
+            inflight: Arc::clone(&self.inflight),
+            completed: false,
+        };
+
+        self.write_request(id, &req).await?;
+
+        let result = rx.await.unwrap_or(Err(RpcError::ConnectionClosed));
+        guard.completed = true;
+
+        result
+    }
+
+    /// Sends `req` and returns a stream of every response the server emits
+    /// for it, ending cleanly when the server's `EndOfStream` frame arrives.
+    /// Dropping the stream early sends the server a `Cancel` frame so it can
+    /// stop driving that request id's handler stream, in addition to
+    /// removing the id from this client's in-flight map.
+    pub async fn call_streaming(
+        &self,
+        req: Req,
+    ) -> Result<StreamingResponse<Resp, W>, RpcError> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        self.inflight
+            .lock()
+            .unwrap()
+            .insert(id, Pending::Streaming(tx));
+
+        if let Err(e) = self.write_request(id, &req).await {
+            self.inflight.lock().unwrap().remove(&id);
+            return Err(e);
+        }
+
+        Ok(StreamingResponse {
+            id,
+            inflight: Arc::clone(&self.inflight),
+            write: Arc::clone(&self.write),
+            rx,
+        })
+    }
+
+    async fn write_request(&self, id: u64, req: &Req) -> Result<(), RpcError> {
+        let payload = E::encode(req).map_err(|e| Box::new(e) as _).context(EncodeSnafu)?;
+
+        let mut write = self.write.lock().await;
+        write_frame(&mut *write, id, FrameKind::Request, &payload)
+            .await
+            .context(WriteSnafu)
+    }
+}
+
+/// A stream of responses correlated to a single [`Client::call_streaming`]
+/// request id. Ends when the server's `EndOfStream` frame arrives.
+pub struct StreamingResponse<Resp, W> {
+    id: u64,
+    inflight: InflightMap<Resp>,
+    write: Arc<tokio::sync::Mutex<W>>,
+    rx: mpsc::UnboundedReceiver<Result<Resp, RpcError>>,
+}
+
+impl<Resp, W> Stream for StreamingResponse<Resp, W> {
+    type Item = Result<Resp, RpcError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Option<Self::Item>> {
+        self.rx.poll_recv(cx)
+    }
+}
+
+impl<Resp, W> Drop for StreamingResponse<Resp, W>
+where
+    Resp: 'static + Send,
+    W: 'static + AsyncWrite + Unpin + Send,
+{
+    /// Removes this id from the in-flight map and, best-effort, tells the
+    /// server to stop driving its handler stream for it — dropping the
+    /// stream early is the only signal the server gets that nobody is going
+    /// to read the rest of it. If there's no Tokio runtime to spawn the send
+    /// on (e.g. this is dropped during forced executor shutdown), the cancel
+    /// is silently skipped rather than panicking.
+    fn drop(&mut self) {
+        self.inflight.lock().unwrap().remove(&self.id);
+
+        let Ok(handle) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+
+        let id = self.id;
+        let write = Arc::clone(&self.write);
+        handle.spawn(async move {
+            let mut write = write.lock().await;
+            let _ = write_frame(&mut *write, id, FrameKind::Cancel, &[]).await;
+        });
+    }
+}
+
+async fn serve_connection<Req, Resp, E, F, Fut, R, W>(
+    mut read: R,
+    write: W,
+    handler: F,
+) -> std::io::Result<()>
+where
+    Req: 'static + Send,
+    Resp: 'static + Send,
+    E: 'static + DecodeMethod<Req> + EncodeMethod<Resp>,
+    F: 'static + Clone + Send + Fn(Req) -> Fut,
+    Fut: 'static + Send + Future<Output = Resp>,
+    R: AsyncRead + Unpin,
+    W: 'static + AsyncWrite + Unpin + Send,
+{
+    let write = Arc::new(tokio::sync::Mutex::new(write));
+
+    while let Some(frame) = read_frame(&mut read).await? {
+        let Frame { id, kind, payload } = frame;
+        if kind != FrameKind::Request {
+            continue;
+        }
+
+        let handler = handler.clone();
+        let write = Arc::clone(&write);
+
+        tokio::spawn(async move {
+            let (kind, payload) = match E::decode(payload) {
+                Ok(req) => {
+                    let resp = handler(req).await;
+                    match E::encode(&resp) {
+                        Ok(bytes) => (FrameKind::Response, bytes.to_vec()),
+                        Err(e) => (FrameKind::Error, e.to_string().into_bytes()),
+                    }
+                }
+                Err(e) => (FrameKind::Error, e.to_string().into_bytes()),
+            };
+
+            let mut write = write.lock().await;
+            let _ = write_frame(&mut *write, id, kind, &payload).await;
+        });
+    }
+
+    Ok(())
+}
+
+/// Binds `local_addr` and serves every accepted connection with a streaming
+/// `handler`, one task per request so a long-running stream on one request
+/// id can't block responses for another.
+pub async fn serve_streaming_on<A, Req, Resp, E, F, S>(
+    local_addr: A,
+    handler: F,
+) -> Result<(), ServeError>
+where
+    A: ToSocketAddrs,
+    Req: 'static + Send,
+    Resp: 'static + Send,
+    E: 'static + DecodeMethod<Req> + EncodeMethod<Resp>,
+    F: 'static + Clone + Send + Fn(Req) -> S,
+    S: 'static + Send + Stream<Item = Resp>,
+{
+    let listener = TcpListener::bind(local_addr).await.context(BindSnafu)?;
+
+    loop {
+        let (stream, _addr) = listener.accept().await.context(AcceptSnafu)?;
+        let (read, write) = stream.into_split();
+        let handler = handler.clone();
+
+        tokio::spawn(async move {
+            let _ = serve_connection_streaming::<Req, Resp, E, F, S, _, _>(read, write, handler).await;
+        });
+    }
+}
+
+async fn serve_connection_streaming<Req, Resp, E, F, S, R, W>(
+    mut read: R,
+    write: W,
+    handler: F,
+) -> std::io::Result<()>
+where
+    Req: 'static + Send,
+    Resp: 'static + Send,
+    E: 'static + DecodeMethod<Req> + EncodeMethod<Resp>,
+    F: 'static + Clone + Send + Fn(Req) -> S,
+    S: 'static + Send + Stream<Item = Resp>,
+    R: AsyncRead + Unpin,
+    W: 'static + AsyncWrite + Unpin + Send,
+{
+    let write = Arc::new(tokio::sync::Mutex::new(write));
+    let cancels: Arc<Mutex<HashMap<u64, oneshot::Sender<()>>>> = Arc::new(Mutex::new(HashMap::new()));
+
+    while let Some(frame) = read_frame(&mut read).await? {
+        let Frame { id, kind, payload } = frame;
+
+        match kind {
+            FrameKind::Cancel => {
+                if let Some(tx) = cancels.lock().unwrap().remove(&id) {
+                    let _ = tx.send(());
+                }
+                continue;
+            }
+            FrameKind::Request => {}
+            FrameKind::Response | FrameKind::Error | FrameKind::EndOfStream => continue,
+        }
+
+        let handler = handler.clone();
+        let write = Arc::clone(&write);
+        let (cancel_tx, mut cancel_rx) = oneshot::channel();
+        cancels.lock().unwrap().insert(id, cancel_tx);
+        let cancels = Arc::clone(&cancels);
+
+        // Each request id gets its own task; chunks from concurrently active
+        // streams interleave on the shared writer in whatever order they
+        // become ready, so no single stream can starve the others.
+        tokio::spawn(async move {
+            let req = match E::decode(payload) {
+                Ok(req) => req,
+                Err(e) => {
+                    let message = e.to_string();
+                    let mut write = write.lock().await;
+                    let _ = write_frame(&mut *write, id, FrameKind::Error, message.as_bytes()).await;
+                    let _ = write_frame(&mut *write, id, FrameKind::EndOfStream, &[]).await;
+                    cancels.lock().unwrap().remove(&id);
+                    return;
+                }
+            };
+
+            let mut items = Box::pin(handler(req));
+            loop {
+                let item = tokio::select! {
+                    item = items.next() => item,
+                    _ = &mut cancel_rx => break,
+                };
+
+                let item = match item {
+                    Some(item) => item,
+                    None => break,
+                };
+
+                match E::encode(&item) {
+                    Ok(bytes) => {
+                        let mut write = write.lock().await;
+                        if write_frame(&mut *write, id, FrameKind::Response, &bytes)
+                            .await
+                            .is_err()
+                        {
+                            cancels.lock().unwrap().remove(&id);
+                            return;
+                        }
+                    }
+                    Err(e) => {
+                        let message = e.to_string();
+                        let mut write = write.lock().await;
+                        let _ =
+                            write_frame(&mut *write, id, FrameKind::Error, message.as_bytes()).await;
+                        break;
+                    }
+                }
+            }
+
+            // Always emit EndOfStream, even after an error, a cancel, or the
+            // handler running dry, so the client's stream terminates cleanly
+            // (a cancelling client has already dropped its `StreamingResponse`
+            // and won't read this, but a well-formed stream still ends).
+            cancels.lock().unwrap().remove(&id);
+            let mut write = write.lock().await;
+            let _ = write_frame(&mut *write, id, FrameKind::EndOfStream, &[]).await;
+        });
+    }
+
+    Ok(())
+}
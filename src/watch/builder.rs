@@ -0,0 +1,62 @@
+use super::{Receiver, Sender};
+use crate::{channel, multi_channel};
+use std::{fmt, net::ToSocketAddrs};
+
+/// Future returned by [`sender_on`](super::sender_on); resolves once the
+/// underlying listener is bound.
+pub type SenderBuilderFuture<A, T, E, RW, Fut, Filter> =
+    multi_channel::builder::MultiChannelListenerBuilderFuture<
+        A,
+        T,
+        E,
+        RW,
+        Fut,
+        Filter,
+        fn(multi_channel::Channel<T, E, 0, RW>) -> Sender<T, E, 0, RW>,
+    >;
+
+/// Future returned by [`receiver_to`](super::receiver_to); resolves once the
+/// connection to the sender is established.
+pub type ReceiverBuilderFuture<A, T, E, RW, Fut, Filter> = channel::builder::ChannelBuilderFuture<
+    A,
+    T,
+    E,
+    RW,
+    Fut,
+    Filter,
+    fn(channel::Channel<T, E, RW>) -> Receiver<T, E, RW>,
+>;
+
+pub(crate) fn new_sender<A, T, E>(
+    local_addr: A,
+) -> SenderBuilderFuture<
+    A,
+    T,
+    E,
+    crate::util::split::TcpSplit,
+    impl std::future::Future<Output = multi_channel::builder::AcceptResult>,
+    impl Clone + Fn(std::net::SocketAddr) -> bool,
+>
+where
+    A: 'static + Clone + Send + ToSocketAddrs,
+    T: 'static + fmt::Debug,
+{
+    multi_channel::builder::new(local_addr).build_with(Sender::from_channel)
+}
+
+pub(crate) fn new_receiver<A, T, E>(
+    dest: A,
+) -> ReceiverBuilderFuture<
+    A,
+    T,
+    E,
+    crate::util::split::TcpSplit,
+    impl std::future::Future<Output = channel::builder::BuildResult<crate::util::split::TcpSplit>>,
+    impl Clone + Fn(std::net::SocketAddr) -> bool,
+>
+where
+    A: 'static + Clone + Send + ToSocketAddrs,
+    T: 'static + fmt::Debug,
+{
+    channel::builder::new(dest).build_with(Receiver::from_channel)
+}
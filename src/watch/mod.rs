@@ -0,0 +1,324 @@
+use crate::{
+    channel, multi_channel,
+    util::{
+        codec::{DecodeMethod, EncodeMethod},
+        split::{RWSplit, TcpSplit},
+    },
+};
+use errors::*;
+use futures::{ready, Future, Sink, Stream};
+use snafu::{Backtrace, ResultExt};
+use std::{
+    fmt,
+    net::{SocketAddr, ToSocketAddrs},
+    sync::{Arc, RwLock},
+    task::Poll,
+};
+use tokio::io::{AsyncRead, AsyncWrite};
+
+pub mod builder;
+
+#[cfg(feature = "json")]
+pub type JsonSender<T, const N: usize = 0> = Sender<T, crate::util::codec::JsonCodec, N>;
+
+#[cfg(feature = "json")]
+pub type JsonReceiver<T> = Receiver<T, crate::util::codec::JsonCodec>;
+
+#[cfg(feature = "protobuf")]
+pub type ProtobufSender<T, const N: usize = 0> = Sender<T, crate::util::codec::ProtobufCodec, N>;
+
+#[cfg(feature = "protobuf")]
+pub type ProtobufReceiver<T> = Receiver<T, crate::util::codec::ProtobufCodec>;
+
+#[cfg(feature = "rkyv")]
+pub type RkyvSender<T, const N: usize = 0> = Sender<T, crate::util::codec::RkyvCodec, N>;
+
+#[cfg(feature = "rkyv")]
+pub type RkyvReceiver<T> = Receiver<T, crate::util::codec::RkyvCodec>;
+
+/// Binds to `local_addr` and accepts subscribers, replaying the most recently
+/// sent value to each one as it joins. Mirrors [`crate::mpsc::recv_on`], except
+/// the listening side here is the one doing the sending.
+pub fn sender_on<A: 'static + Clone + Send + ToSocketAddrs, T, E>(
+    local_addr: A,
+) -> builder::SenderBuilderFuture<
+    A,
+    T,
+    E,
+    TcpSplit,
+    impl Future<Output = multi_channel::builder::AcceptResult>,
+    impl Clone + Fn(SocketAddr) -> bool,
+> {
+    builder::new_sender(local_addr)
+}
+
+/// Connects to a [`Sender`](sender_on) and follows its most recently published
+/// value. Mirrors [`crate::mpsc::send_to`], except the connecting side here is
+/// the one doing the receiving.
+pub fn receiver_to<A: 'static + Clone + Send + ToSocketAddrs, T, E>(
+    dest: A,
+) -> builder::ReceiverBuilderFuture<
+    A,
+    T,
+    E,
+    TcpSplit,
+    impl Future<Output = channel::builder::BuildResult<TcpSplit>>,
+    impl Clone + Fn(SocketAddr) -> bool,
+> {
+    builder::new_receiver(dest)
+}
+
+/// Holds the most recently sent value so it can be replayed to every newly
+/// accepted peer. Peers already connected when a new value is sent don't go
+/// through here at all — they get it live, via `multi_channel::Channel`'s own
+/// fan-out in `start_send`/`poll_flush`; this cache exists solely to catch up
+/// whoever joins afterward.
+struct Latest<T> {
+    value: RwLock<Option<T>>,
+}
+
+impl<T> Default for Latest<T> {
+    fn default() -> Self {
+        Latest {
+            value: RwLock::new(None),
+        }
+    }
+}
+
+#[pin_project::pin_project]
+pub struct Sender<T: fmt::Debug, E, const N: usize = 0, RW = TcpSplit> {
+    #[pin]
+    channel: multi_channel::Channel<T, E, N, RW>,
+    latest: Arc<Latest<T>>,
+}
+
+impl<T, E, const N: usize, RW> Sender<T, E, N, RW>
+where
+    T: fmt::Debug,
+{
+    pub(crate) fn from_channel(channel: multi_channel::Channel<T, E, N, RW>) -> Self {
+        Sender {
+            channel,
+            latest: Arc::new(Latest::default()),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.channel.len()
+    }
+
+    pub fn limit(&self) -> Option<usize> {
+        self.channel.limit()
+    }
+
+    pub fn local_addr(&self) -> &SocketAddr {
+        self.channel.local_addr()
+    }
+
+    pub fn peer_addrs(&self) -> Vec<SocketAddr> {
+        self.channel.peer_addrs()
+    }
+}
+
+impl<T, E, const N: usize> Sender<T, E, N>
+where
+    T: 'static + Clone + fmt::Debug,
+    E: 'static + EncodeMethod<T>,
+{
+    /// Accepts a new subscriber and immediately replays the cached value to it,
+    /// if one has been sent yet.
+    pub async fn accept(&mut self) -> Result<SocketAddr, SenderAcceptingError<TcpSplit>> {
+        let addr = self
+            .channel
+            .accept()
+            .await
+            .context(SenderAcceptingSnafu)?;
+
+        let cached = self.latest.value.read().unwrap().clone();
+        if let Some(item) = cached {
+            self.channel
+                .send_to(&addr, item)
+                .await
+                .context(SenderAcceptingSnafu)?;
+        }
+
+        Ok(addr)
+    }
+}
+
+impl<T, E, const N: usize, RW> Sink<T> for Sender<T, E, N, RW>
+where
+    T: 'static + Clone + fmt::Debug,
+    E: 'static + EncodeMethod<T>,
+    RW: AsyncWrite,
+{
+    type Error = SenderError<T, E>;
+
+    fn start_send(self: std::pin::Pin<&mut Self>, item: T) -> Result<(), Self::Error> {
+        let this = self.project();
+
+        *this.latest.value.write().unwrap() = Some(item.clone());
+
+        this.channel.start_send(item).context(SenderSnafu)
+    }
+
+    fn poll_ready(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        if let Err(e) = ready!(self.project().channel.poll_ready(cx)) {
+            return Poll::Ready(Err(e).context(SenderSnafu));
+        }
+
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_flush(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        if let Err(e) = ready!(self.project().channel.poll_flush(cx)) {
+            return Poll::Ready(Err(e).context(SenderSnafu));
+        }
+
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        if let Err(e) = ready!(self.project().channel.poll_close(cx)) {
+            return Poll::Ready(Err(e).context(SenderSnafu));
+        }
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[pin_project::pin_project]
+pub struct Receiver<T: fmt::Debug, E, RW = TcpSplit> {
+    #[pin]
+    channel: channel::Channel<T, E, RW>,
+    current: Option<T>,
+}
+
+impl<T, E, RW> Receiver<T, E, RW>
+where
+    T: fmt::Debug,
+{
+    pub(crate) fn from_channel(channel: channel::Channel<T, E, RW>) -> Self {
+        Receiver {
+            channel,
+            current: None,
+        }
+    }
+
+    pub fn local_addr(&self) -> &SocketAddr {
+        &self.channel.local_addr()
+    }
+
+    pub fn peer_addr(&self) -> &SocketAddr {
+        &self.channel.peer_addr()
+    }
+
+    /// Returns the most recently observed value without waiting for a new one.
+    pub fn borrow(&self) -> Option<&T> {
+        self.current.as_ref()
+    }
+}
+
+impl<T, E, RW> Receiver<T, E, RW>
+where
+    T: 'static + Clone + fmt::Debug,
+    E: 'static + DecodeMethod<T>,
+    RW: 'static + fmt::Debug + AsyncRead + Unpin,
+{
+    /// Waits for the value to change and returns the latest one. Any updates
+    /// that piled up on the socket while this receiver was busy are drained
+    /// so only the newest is ever surfaced.
+    pub async fn recv(&mut self) -> Option<Result<T, ReceiverError<T, E>>> {
+        use futures::future::poll_fn;
+
+        poll_fn(|cx| {
+            <Self as Stream>::poll_next(std::pin::Pin::new(self), cx)
+        })
+        .await
+    }
+}
+
+impl<T, E, RW> Stream for Receiver<T, E, RW>
+where
+    T: 'static + Clone + fmt::Debug,
+    E: 'static + DecodeMethod<T>,
+    RW: 'static + fmt::Debug + AsyncRead + Unpin,
+{
+    type Item = Result<T, ReceiverError<T, E>>;
+
+    fn poll_next(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+
+        let mut latest = match ready!(this.channel.as_mut().poll_next(cx)) {
+            Some(Ok(item)) => item,
+            Some(Err(e)) => return Poll::Ready(Some(Err(e).context(ReceiverSnafu))),
+            None => return Poll::Ready(None),
+        };
+
+        // Coalesce: keep decoding while the socket already has the next
+        // frame ready, so a busy receiver only ever sees the newest value.
+        while let Poll::Ready(next) = this.channel.as_mut().poll_next(cx) {
+            match next {
+                Some(Ok(item)) => {
+                    latest = item;
+                }
+                Some(Err(e)) => return Poll::Ready(Some(Err(e).context(ReceiverSnafu))),
+                None => break,
+            }
+        }
+
+        *this.current = Some(latest.clone());
+        Poll::Ready(Some(Ok(latest)))
+    }
+}
+
+pub mod errors {
+    use super::*;
+    use snafu::Snafu;
+
+    #[derive(Debug, Snafu)]
+    #[snafu(display("[SenderAcceptingError] Failed to accept subscriber"))]
+    #[snafu(visibility(pub(super)))]
+    pub struct SenderAcceptingError<T: 'static + fmt::Debug> {
+        source: multi_channel::errors::AcceptingError<T>,
+        backtrace: Backtrace,
+    }
+
+    #[derive(Debug, Snafu)]
+    #[snafu(display("[SenderError] Failed to send item on watch::Sender"))]
+    #[snafu(visibility(pub(super)))]
+    pub struct SenderError<T, E>
+    where
+        T: 'static + fmt::Debug,
+        E: 'static + EncodeMethod<T>,
+        E::Error: 'static + fmt::Debug + std::error::Error,
+    {
+        source: multi_channel::errors::ChannelSinkError<T, E>,
+        backtrace: Backtrace,
+    }
+
+    #[derive(Debug, Snafu)]
+    #[snafu(display("[ReceiverError] Failed to receive item on watch::Receiver"))]
+    #[snafu(visibility(pub(super)))]
+    pub struct ReceiverError<T, E>
+    where
+        T: 'static + fmt::Debug,
+        E: 'static + DecodeMethod<T>,
+        E::Error: 'static + fmt::Debug + std::error::Error,
+    {
+        source: channel::errors::ChannelStreamError<T, E>,
+        backtrace: Backtrace,
+    }
+}